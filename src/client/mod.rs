@@ -0,0 +1,87 @@
+mod qbittorrent;
+mod transmission;
+
+pub use qbittorrent::QBittorrentClient;
+pub use transmission::TransmissionClient;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::multipart::Part;
+use serde::Deserialize;
+
+/// Common surface every torrent daemon backend (qBittorrent, Transmission, ...) implements,
+/// so the bot's Telegram-facing code never has to know which one it's talking to.
+#[async_trait]
+pub trait DownloadClient: Send + Sync {
+    async fn login(&self) -> anyhow::Result<()>;
+
+    async fn add(&self, request_type: &RequestType<'_>, options: &AddOptions) -> anyhow::Result<()>;
+
+    async fn list(&self) -> anyhow::Result<Vec<TorrentInfo>>;
+
+    async fn pause(&self, hash: &str) -> anyhow::Result<()>;
+
+    async fn resume(&self, hash: &str) -> anyhow::Result<()>;
+
+    async fn remove(&self, hash: &str, delete_files: bool) -> anyhow::Result<()>;
+
+    /// Category names available for new torrents. Backends without a native notion of
+    /// categories (e.g. Transmission) can leave this at the default empty list.
+    async fn categories(&self) -> anyhow::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TorrentInfo {
+    pub hash: String,
+    pub name: String,
+    pub state: String,
+    pub progress: f64,
+    pub dlspeed: i64,
+    pub eta: i64,
+    pub size: i64,
+}
+
+pub enum RequestType<'a> {
+    Url(&'a str),
+    File(&'a Bytes),
+}
+
+impl<'a> RequestType<'a> {
+    fn to_part(&self) -> anyhow::Result<(&'static str, Part)> {
+        match *self {
+            RequestType::Url(url) => Ok(("urls", Part::bytes(url.as_bytes().to_vec()))),
+            RequestType::File(file) => Ok((
+                "torrents",
+                Part::bytes(file.to_vec())
+                    .file_name("torrent.torrent")
+                    .mime_str("application/x-bittorrent")?,
+            )),
+        }
+    }
+}
+
+/// An owned counterpart of [`RequestType`], held onto while a user picks a destination category.
+pub enum PendingTorrent {
+    Url(String),
+    File(Bytes),
+}
+
+impl PendingTorrent {
+    pub fn as_request_type(&self) -> RequestType<'_> {
+        match self {
+            PendingTorrent::Url(url) => RequestType::Url(url),
+            PendingTorrent::File(file) => RequestType::File(file),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AddOptions {
+    pub category: Option<String>,
+    pub save_path: Option<String>,
+    pub paused: Option<bool>,
+    pub sequential_download: Option<bool>,
+    pub rename: Option<String>,
+}
@@ -0,0 +1,241 @@
+use crate::client::{AddOptions, DownloadClient, RequestType, TorrentInfo};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use reqwest::{multipart, Client, Response, StatusCode, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use tokio::sync::RwLock;
+
+pub struct QBittorrentClient {
+    http_client: Client,
+    base_url: Url,
+    username: String,
+    password: String,
+    authenticated: RwLock<bool>,
+}
+
+impl QBittorrentClient {
+    pub fn new<S: ToString>(url: S, username: S, password: S) -> anyhow::Result<Self> {
+        let http_client = Client::builder().cookie_store(true).build()?;
+        let base_url = Url::parse(&url.to_string())?;
+
+        Ok(QBittorrentClient {
+            http_client,
+            base_url,
+            username: username.to_string(),
+            password: password.to_string(),
+            authenticated: RwLock::new(false),
+        })
+    }
+
+    fn build_url(&self, endpoint: &str) -> anyhow::Result<Url> {
+        Ok(self.base_url.join(endpoint)?)
+    }
+
+    async fn ensure_authenticated(&self) -> anyhow::Result<()> {
+        if *self.authenticated.read().await {
+            return Ok(());
+        }
+
+        self.login_impl().await
+    }
+
+    async fn login_impl(&self) -> anyhow::Result<()> {
+        let login_url = self.base_url.join("/api/v2/auth/login")?;
+
+        let res = self
+            .http_client
+            .post(login_url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Referer", self.base_url.to_string())
+            .body(format!(
+                "username={}&password={}",
+                self.username, self.password
+            ))
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(anyhow!("Auth failed"));
+        }
+
+        *self.authenticated.write().await = true;
+
+        Ok(())
+    }
+
+    /// Runs a request, transparently re-logging in and retrying once if the session cookie
+    /// qBittorrent cached turned out to be stale (signalled by a 403 response).
+    async fn execute_with_reauth<F, Fut>(&self, build_request: F) -> anyhow::Result<Response>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = anyhow::Result<Response>>,
+    {
+        self.ensure_authenticated().await?;
+
+        let response = build_request().await?;
+        if response.status() != StatusCode::FORBIDDEN {
+            return Ok(response);
+        }
+
+        *self.authenticated.write().await = false;
+        self.login_impl().await?;
+
+        build_request().await
+    }
+
+    async fn post_form(&self, endpoint: &str, form: &[(&str, &str)]) -> anyhow::Result<()> {
+        let url = self.build_url(endpoint)?;
+
+        let body = form
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let res = self
+            .execute_with_reauth(|| async {
+                Ok(self
+                    .http_client
+                    .post(url.clone())
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .body(body.clone())
+                    .send()
+                    .await?)
+            })
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Request to {} failed: {:?}",
+                endpoint,
+                res.text().await?
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DownloadClient for QBittorrentClient {
+    async fn login(&self) -> anyhow::Result<()> {
+        self.ensure_authenticated().await
+    }
+
+    async fn add(&self, request_type: &RequestType<'_>, options: &AddOptions) -> anyhow::Result<()> {
+        let url = self.build_url("/api/v2/torrents/add")?;
+
+        let res = self
+            .execute_with_reauth(|| async {
+                let (name, part) = request_type.to_part()?;
+                let multipart = options.apply(multipart::Form::new().part(name, part));
+                Ok(self.http_client.post(url.clone()).multipart(multipart).send().await?)
+            })
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Error adding new torrent with file {:?}",
+                res.text().await?
+            ));
+        }
+
+        let text = res.text().await?;
+        if text != "Ok." {
+            return Err(anyhow!(
+                "Error adding new torrent with file, not Ok., but {}",
+                text
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<TorrentInfo>> {
+        let url = self.build_url("/api/v2/torrents/info")?;
+
+        let res = self
+            .execute_with_reauth(|| async { Ok(self.http_client.get(url.clone()).send().await?) })
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Error fetching torrents info: {:?}",
+                res.text().await?
+            ));
+        }
+
+        Ok(res.json::<Vec<TorrentInfo>>().await?)
+    }
+
+    async fn pause(&self, hash: &str) -> anyhow::Result<()> {
+        self.post_form("/api/v2/torrents/pause", &[("hashes", hash)])
+            .await
+    }
+
+    async fn resume(&self, hash: &str) -> anyhow::Result<()> {
+        self.post_form("/api/v2/torrents/resume", &[("hashes", hash)])
+            .await
+    }
+
+    async fn remove(&self, hash: &str, delete_files: bool) -> anyhow::Result<()> {
+        self.post_form(
+            "/api/v2/torrents/delete",
+            &[
+                ("hashes", hash),
+                ("deleteFiles", if delete_files { "true" } else { "false" }),
+            ],
+        )
+            .await
+    }
+
+    async fn categories(&self) -> anyhow::Result<Vec<String>> {
+        let url = self.build_url("/api/v2/torrents/categories")?;
+
+        let res = self
+            .execute_with_reauth(|| async { Ok(self.http_client.get(url.clone()).send().await?) })
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Error fetching categories: {:?}",
+                res.text().await?
+            ));
+        }
+
+        let categories = res.json::<HashMap<String, Category>>().await?;
+        Ok(categories.into_keys().collect())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Category {
+    #[allow(dead_code)]
+    name: String,
+    #[allow(dead_code)]
+    #[serde(rename = "savePath")]
+    save_path: String,
+}
+
+impl AddOptions {
+    fn apply(&self, mut form: multipart::Form) -> multipart::Form {
+        if let Some(category) = &self.category {
+            form = form.text("category", category.clone());
+        }
+        if let Some(save_path) = &self.save_path {
+            form = form.text("savepath", save_path.clone());
+        }
+        if let Some(paused) = self.paused {
+            form = form.text("paused", paused.to_string());
+        }
+        if let Some(sequential_download) = self.sequential_download {
+            form = form.text("sequentialDownload", sequential_download.to_string());
+        }
+        if let Some(rename) = &self.rename {
+            form = form.text("rename", rename.clone());
+        }
+        form
+    }
+}
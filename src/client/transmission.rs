@@ -0,0 +1,202 @@
+use crate::client::{AddOptions, DownloadClient, RequestType, TorrentInfo};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::{Client, StatusCode, Url};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+/// Talks to a Transmission daemon's RPC endpoint (a single JSON-over-HTTP method dispatcher,
+/// see <https://github.com/transmission/transmission/blob/main/docs/rpc-spec.md>).
+pub struct TransmissionClient {
+    http_client: Client,
+    rpc_url: Url,
+    username: Option<String>,
+    password: Option<String>,
+    session_id: RwLock<Option<String>>,
+}
+
+impl TransmissionClient {
+    pub fn new<S: ToString>(
+        url: S,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let rpc_url = Url::parse(&url.to_string())?.join("transmission/rpc")?;
+
+        Ok(TransmissionClient {
+            http_client: Client::new(),
+            rpc_url,
+            username,
+            password,
+            session_id: RwLock::new(None),
+        })
+    }
+
+    fn authenticate(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            request = request.basic_auth(username, Some(password));
+        }
+        request
+    }
+
+    /// Transmission hands out a session id on first contact and requires it on every
+    /// subsequent call; a stale one is signalled by a 409 response carrying a fresh id.
+    async fn refresh_session_id(&self) -> anyhow::Result<String> {
+        let request = self.authenticate(self.http_client.post(self.rpc_url.clone()));
+        let res = request.send().await?;
+
+        let session_id = res
+            .headers()
+            .get("X-Transmission-Session-Id")
+            .ok_or_else(|| anyhow!("Transmission did not return a session id"))?
+            .to_str()?
+            .to_string();
+
+        *self.session_id.write().await = Some(session_id.clone());
+
+        Ok(session_id)
+    }
+
+    async fn call(&self, method: &str, arguments: Value) -> anyhow::Result<Value> {
+        let body = json!({ "method": method, "arguments": arguments });
+
+        let session_id = match self.session_id.read().await.clone() {
+            Some(session_id) => session_id,
+            None => self.refresh_session_id().await?,
+        };
+
+        let send_with = |session_id: &str| {
+            self.authenticate(
+                self.http_client
+                    .post(self.rpc_url.clone())
+                    .header("X-Transmission-Session-Id", session_id),
+            )
+                .json(&body)
+        };
+
+        let res = send_with(&session_id).send().await?;
+
+        let res = if res.status() == StatusCode::CONFLICT {
+            let session_id = self.refresh_session_id().await?;
+            send_with(&session_id).send().await?
+        } else {
+            res
+        };
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Transmission call {} failed: {:?}",
+                method,
+                res.text().await?
+            ));
+        }
+
+        let mut response: Value = res.json().await?;
+        if response["result"].as_str() != Some("success") {
+            return Err(anyhow!(
+                "Transmission call {} failed: {:?}",
+                method,
+                response["result"]
+            ));
+        }
+
+        Ok(response["arguments"].take())
+    }
+}
+
+#[async_trait]
+impl DownloadClient for TransmissionClient {
+    async fn login(&self) -> anyhow::Result<()> {
+        self.refresh_session_id().await?;
+        Ok(())
+    }
+
+    async fn add(&self, request_type: &RequestType<'_>, options: &AddOptions) -> anyhow::Result<()> {
+        let mut arguments = match request_type {
+            RequestType::Url(url) => json!({ "filename": url }),
+            RequestType::File(file) => json!({ "metainfo": BASE64.encode(file.as_ref()) }),
+        };
+
+        if let Some(save_path) = &options.save_path {
+            arguments["download-dir"] = json!(save_path);
+        }
+        if let Some(paused) = options.paused {
+            arguments["paused"] = json!(paused);
+        }
+
+        self.call("torrent-add", arguments).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<TorrentInfo>> {
+        let arguments = self
+            .call(
+                "torrent-get",
+                json!({
+                    "fields": ["hashString", "name", "status", "percentDone", "rateDownload", "eta", "totalSize"],
+                }),
+            )
+            .await?;
+
+        let torrents = arguments["torrents"].as_array().cloned().unwrap_or_default();
+
+        Ok(torrents.iter().map(torrent_info_from_json).collect())
+    }
+
+    async fn pause(&self, hash: &str) -> anyhow::Result<()> {
+        self.call("torrent-stop", json!({ "ids": [hash] })).await?;
+        Ok(())
+    }
+
+    async fn resume(&self, hash: &str) -> anyhow::Result<()> {
+        self.call("torrent-start", json!({ "ids": [hash] })).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, hash: &str, delete_files: bool) -> anyhow::Result<()> {
+        self.call(
+            "torrent-remove",
+            json!({ "ids": [hash], "delete-local-data": delete_files }),
+        )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Maps a Transmission `status` code to the qBittorrent-style state names the rest of the
+/// bot already knows how to render and treat as "completed" (see `is_completed_state`).
+///
+/// Transmission's status alone doesn't say whether a torrent is finished — status `0`
+/// (stopped) and `3` (queued) cover both complete and incomplete torrents — so completion
+/// is derived from `percent_done` instead of inferred from the status code.
+fn torrent_state_name(status: i64, percent_done: f64) -> &'static str {
+    let done = percent_done >= 1.0;
+    match status {
+        0 if done => "pausedUP",
+        0 => "pausedDL",
+        1 | 2 => "checkingUP",
+        3 if done => "queuedUP",
+        3 => "queuedDL",
+        4 => "downloading",
+        5 => "queuedUP",
+        6 => "uploading",
+        _ => "unknown",
+    }
+}
+
+fn torrent_info_from_json(value: &Value) -> TorrentInfo {
+    let percent_done = value["percentDone"].as_f64().unwrap_or_default();
+
+    TorrentInfo {
+        hash: value["hashString"].as_str().unwrap_or_default().to_string(),
+        name: value["name"].as_str().unwrap_or_default().to_string(),
+        state: torrent_state_name(value["status"].as_i64().unwrap_or_default(), percent_done)
+            .to_string(),
+        progress: percent_done,
+        dlspeed: value["rateDownload"].as_i64().unwrap_or_default(),
+        eta: value["eta"].as_i64().unwrap_or(-1),
+        size: value["totalSize"].as_i64().unwrap_or_default(),
+    }
+}
@@ -1,17 +1,20 @@
 mod client;
 mod state;
+mod storage;
 mod util;
 
-use crate::client::{QBittorrentClient, RequestType};
+use crate::client::{AddOptions, PendingTorrent, RequestType, TorrentInfo};
 use crate::state::BotState;
-use crate::util::{run_in_docker, ResultExt};
+use crate::util::{format_eta, format_speed, render_progress_bar, run_in_docker, ResultExt};
 use anyhow::anyhow;
 use bytes::Bytes;
 use config::{Config, Environment};
 use dotenvy::dotenv;
-use log::{info, warn, LevelFilter};
+use log::{error, info, warn, LevelFilter};
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::ops::Deref;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use telers::methods::{AnswerCallbackQuery, GetFile, SendMessage};
 use telers::types::message::{Document, Text};
 use telers::types::{CallbackQuery, ChatIdKind, InlineKeyboardButton, InlineKeyboardMarkup};
@@ -41,15 +44,23 @@ async fn run_bot(configuration: &Configuration) -> anyhow::Result<()> {
     let mut router = Router::new("main");
 
     let configuration = configuration.clone();
+    let state = BotState::new(configuration.clone()).await?;
+    router.message.outer_middlewares.register(state.clone());
+
     router
-        .message
+        .callback_query
         .outer_middlewares
-        .register(BotState::new(configuration)?);
-
+        .register(state.clone());
     router.callback_query.register(commands_callback_handler);
     router.message.register(commands_handler);
     router.message.register(torrents_handler);
 
+    tokio::spawn(poll_completed_torrents(
+        bot.clone(),
+        state,
+        configuration.poll_interval_seconds.unwrap_or(30),
+    ));
+
     let dispatcher = Dispatcher::builder()
         .main_router(router)
         .bot(bot)
@@ -64,24 +75,212 @@ async fn run_bot(configuration: &Configuration) -> anyhow::Result<()> {
         .await?)
 }
 
-async fn commands_callback_handler(bot: Bot, callback: CallbackQuery) -> HandlerResult {
+async fn poll_completed_torrents(bot: Bot, state: BotState, interval_seconds: u64) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_seconds.max(1)));
+
+    loop {
+        ticker.tick().await;
+
+        if let Err(err) = check_torrents_progress(&bot, &state).await {
+            error!("{:?}", err);
+        }
+    }
+}
+
+async fn check_torrents_progress(bot: &Bot, state: &BotState) -> anyhow::Result<()> {
+    for torrent in state.download_client.list().await? {
+        let previous_state = state.update_tracked_state(&torrent.hash, &torrent.state).await;
+        let _ = state
+            .storage
+            .update_state(&torrent.hash, &torrent.state)
+            .await
+            .log_error();
+        let was_completed = previous_state.as_deref().is_some_and(is_completed_state);
+
+        if is_completed_state(&torrent.state) && !was_completed {
+            if let Some(owner_user_id) = state.owner_of(&torrent.hash).await {
+                bot.send(SendMessage::new(
+                    ChatIdKind::id(owner_user_id),
+                    format!("✅Загрузка завершена: {}", torrent.name),
+                ))
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_completed_state(state: &str) -> bool {
+    matches!(
+        state,
+        "uploading" | "stalledUP" | "queuedUP" | "forcedUP" | "pausedUP"
+    )
+}
+
+async fn commands_callback_handler(
+    bot: Bot,
+    callback: CallbackQuery,
+    state: BotState,
+) -> HandlerResult {
     bot.send(AnswerCallbackQuery::new(callback.id.clone()))
         .await?;
 
-    match &callback.data {
-        Some(data) if data.as_ref() == "shutdown" => {
-            bot.send(SendMessage::new(
-                ChatIdKind::id(callback.chat_id().unwrap().clone()),
-                "Выключение...",
-            ))
+    if !state.user_allowed(callback.from.id) {
+        warn!("Unknown user id: {}", callback.from.id);
+        return Ok(EventReturn::Finish);
+    }
+
+    let chat_id = callback.chat_id().map(|id| ChatIdKind::id(id.clone()));
+
+    match callback.data.as_deref() {
+        Some("shutdown") => {
+            if let Some(chat_id) = chat_id {
+                bot.send(SendMessage::new(chat_id, "Выключение...")).await?;
+            }
+        }
+        Some(data) if data.starts_with("pause:") => {
+            handle_torrent_action(
+                &bot,
+                &state,
+                chat_id,
+                &data["pause:".len()..],
+                TorrentAction::Pause,
+            )
+                .await?;
+        }
+        Some(data) if data.starts_with("resume:") => {
+            handle_torrent_action(
+                &bot,
+                &state,
+                chat_id,
+                &data["resume:".len()..],
+                TorrentAction::Resume,
+            )
                 .await?;
         }
+        Some(data) if data.starts_with("category:") => {
+            let rest = &data["category:".len()..];
+            if let Some((pending_id, category_index)) = rest.split_once(':') {
+                if let Ok(pending_id) = pending_id.parse::<u64>() {
+                    finalize_pending_torrent(&bot, &state, chat_id, pending_id, category_index)
+                        .await?;
+                }
+            }
+        }
+        Some(data) if data.starts_with("delete_confirm:") => {
+            handle_torrent_action(
+                &bot,
+                &state,
+                chat_id,
+                &data["delete_confirm:".len()..],
+                TorrentAction::Delete,
+            )
+                .await?;
+        }
+        Some("delete_cancel") => {
+            if let Some(chat_id) = chat_id {
+                bot.send(SendMessage::new(chat_id, "Удаление отменено"))
+                    .await?;
+            }
+        }
+        Some(data) if data.starts_with("delete:") => {
+            let hash = &data["delete:".len()..];
+            if let Some(chat_id) = chat_id {
+                bot.send(
+                    SendMessage::new(chat_id, format!("Удалить торрент {}?", hash)).reply_markup(
+                        InlineKeyboardMarkup::new(vec![vec![
+                            InlineKeyboardButton::new("✅Да")
+                                .callback_data(format!("delete_confirm:{hash}")),
+                            InlineKeyboardButton::new("❌Отмена").callback_data("delete_cancel"),
+                        ]]),
+                    ),
+                )
+                    .await?;
+            }
+        }
         _ => {}
     }
 
     Ok(EventReturn::Finish)
 }
 
+enum TorrentAction {
+    Pause,
+    Resume,
+    Delete,
+}
+
+async fn handle_torrent_action(
+    bot: &Bot,
+    state: &BotState,
+    chat_id: Option<ChatIdKind>,
+    hash: &str,
+    action: TorrentAction,
+) -> anyhow::Result<()> {
+    let (result, success_text): (anyhow::Result<()>, &str) = match action {
+        TorrentAction::Pause => (
+            state.download_client.pause(hash).await,
+            "⏸Торрент поставлен на паузу",
+        ),
+        TorrentAction::Resume => (
+            state.download_client.resume(hash).await,
+            "▶️Торрент возобновлён",
+        ),
+        TorrentAction::Delete => (
+            state.download_client.remove(hash, false).await,
+            "🗑Торрент удалён",
+        ),
+    };
+    let result = result.log_error();
+
+    if let Some(chat_id) = chat_id {
+        let text = if result.is_ok() {
+            success_text
+        } else {
+            "⛔Ошибка выполнения действия"
+        };
+        bot.send(SendMessage::new(chat_id, text)).await?;
+    }
+
+    Ok(())
+}
+
+async fn finalize_pending_torrent(
+    bot: &Bot,
+    state: &BotState,
+    chat_id: Option<ChatIdKind>,
+    pending_id: u64,
+    category_index: &str,
+) -> anyhow::Result<()> {
+    let Some((owner_user_id, pending, categories)) = state.take_pending_torrent(pending_id).await
+    else {
+        return Ok(());
+    };
+
+    let category = category_index
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| categories.into_iter().nth(index));
+
+    let options = AddOptions {
+        category,
+        ..Default::default()
+    };
+
+    let result = add_new_torrent(state, pending.as_request_type(), &options, owner_user_id).await;
+
+    if let Some(chat_id) = chat_id {
+        let text = match result {
+            Ok(_) => "✅Торрент добавлен в очередь",
+            Err(_) => "⛔Ошибка добавления торрента",
+        };
+        bot.send(SendMessage::new(chat_id, text)).await?;
+    }
+
+    Ok(())
+}
+
 async fn commands_handler(bot: Bot, message: Message, state: BotState) -> HandlerResult {
     if let Some(from) = message.from() {
         if !state.user_allowed(from.id) {
@@ -102,6 +301,10 @@ async fn commands_handler(bot: Bot, message: Message, state: BotState) -> Handle
                     .await?;
                 return Ok(EventReturn::Finish);
             }
+            Some(text) if text == "/torrents" => {
+                list_torrents(&bot, &state, message.chat().id(), from.id).await?;
+                return Ok(EventReturn::Finish);
+            }
             _ => return Ok(EventReturn::Skip),
         }
     }
@@ -109,6 +312,106 @@ async fn commands_handler(bot: Bot, message: Message, state: BotState) -> Handle
     Ok(EventReturn::Skip)
 }
 
+async fn list_torrents(
+    bot: &Bot,
+    state: &BotState,
+    chat_id: ChatIdKind,
+    owner_user_id: i64,
+) -> anyhow::Result<()> {
+    let owned_hashes: HashSet<String> = state
+        .storage
+        .list_for_user(owner_user_id)
+        .await?
+        .into_iter()
+        .map(|record| record.infohash)
+        .collect();
+
+    let torrents: Vec<_> = state
+        .download_client
+        .list()
+        .await?
+        .into_iter()
+        .filter(|torrent| owned_hashes.contains(&torrent.hash))
+        .collect();
+
+    if torrents.is_empty() {
+        bot.send(SendMessage::new(chat_id, "Нет активных торрентов"))
+            .await?;
+        return Ok(());
+    }
+
+    for page in torrents.chunks(TORRENTS_PAGE_SIZE) {
+        let text = page
+            .iter()
+            .map(format_torrent_line)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let rows = page.iter().map(|torrent| torrent_keyboard_row(&torrent.hash)).collect();
+
+        bot.send(
+            SendMessage::new(chat_id.clone(), text).reply_markup(InlineKeyboardMarkup::new(rows)),
+        )
+            .await?;
+    }
+
+    Ok(())
+}
+
+const TORRENTS_PAGE_SIZE: usize = 5;
+
+async fn prompt_category(
+    bot: &Bot,
+    state: &BotState,
+    chat_id: ChatIdKind,
+    owner_user_id: i64,
+    pending: PendingTorrent,
+) -> anyhow::Result<()> {
+    let categories = state.download_client.categories().await.unwrap_or_default();
+    let pending_id = state
+        .set_pending_torrent(owner_user_id, pending, categories.clone())
+        .await;
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = categories
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            vec![InlineKeyboardButton::new(name.clone())
+                .callback_data(format!("category:{pending_id}:{index}"))]
+        })
+        .collect();
+    rows.push(vec![
+        InlineKeyboardButton::new("Без категории").callback_data(format!("category:{pending_id}:")),
+    ]);
+
+    bot.send(
+        SendMessage::new(chat_id, "Выберите категорию для торрента")
+            .reply_markup(InlineKeyboardMarkup::new(rows)),
+    )
+        .await?;
+
+    Ok(())
+}
+
+fn torrent_keyboard_row(hash: &str) -> Vec<InlineKeyboardButton> {
+    vec![
+        InlineKeyboardButton::new("⏸Пауза").callback_data(format!("pause:{hash}")),
+        InlineKeyboardButton::new("▶️Возобновить").callback_data(format!("resume:{hash}")),
+        InlineKeyboardButton::new("🗑Удалить").callback_data(format!("delete:{hash}")),
+    ]
+}
+
+fn format_torrent_line(torrent: &TorrentInfo) -> String {
+    format!(
+        "{} {}%\n{}\n⚡{} ⏳{}",
+        render_progress_bar(torrent.progress, 6),
+        (torrent.progress * 100.0).round() as i32,
+        torrent.name,
+        format_speed(torrent.dlspeed),
+        format_eta(torrent.eta)
+    )
+}
+
 async fn torrents_handler(bot: Bot, message: Message, state: BotState) -> HandlerResult {
     if let Some(from) = message.from() {
         if !state.user_allowed(from.id) {
@@ -117,14 +420,16 @@ async fn torrents_handler(bot: Bot, message: Message, state: BotState) -> Handle
         }
 
         let result = match &message {
-            Message::Document(document) => add_torrent_by_file(&bot, &state, document)
-                .await
-                .map(|_| Income::Enqueued),
+            Message::Document(document) => {
+                add_torrent_by_file(&bot, &state, document, message.chat().id(), from.id)
+                    .await
+                    .map(|_| Income::AwaitingCategory)
+            }
             Message::Text(text) => {
                 if text.text.starts_with("magnet:?") {
-                    add_torrent_by_magnet(&state, &text)
+                    add_torrent_by_magnet(&bot, &state, text, message.chat().id(), from.id)
                         .await
-                        .map(|_| Income::Enqueued)
+                        .map(|_| Income::AwaitingCategory)
                 } else {
                     warn!("Unexpected text message received: {}", text.text);
                     Ok(Income::Skipped)
@@ -134,16 +439,11 @@ async fn torrents_handler(bot: Bot, message: Message, state: BotState) -> Handle
         }
             .log_error();
 
-        let text = match result {
-            Ok(income) => match income {
-                Income::Enqueued => Some("✅Торрент добавлен в очередь"),
-                Income::Skipped => None,
-            },
-            Err(_) => Some("⛔Ошибка добавления торрента"),
-        };
-
-        if let Some(text) = text {
-            bot.send(SendMessage::new(message.chat().id(), text))
+        if result.is_err() {
+            bot.send(SendMessage::new(
+                message.chat().id(),
+                "⛔Ошибка добавления торрента",
+            ))
                 .await?;
         }
     }
@@ -152,28 +452,41 @@ async fn torrents_handler(bot: Bot, message: Message, state: BotState) -> Handle
 }
 
 enum Income {
-    Enqueued,
+    AwaitingCategory,
     Skipped,
 }
 
-async fn add_torrent_by_magnet(state: &BotState, text: &Box<Text>) -> anyhow::Result<()> {
-    add_new_torrent(&state, RequestType::Url(text.text.as_ref())).await?;
-    Ok(())
+async fn add_torrent_by_magnet(
+    bot: &Bot,
+    state: &BotState,
+    text: &Box<Text>,
+    chat_id: ChatIdKind,
+    owner_user_id: i64,
+) -> anyhow::Result<()> {
+    prompt_category(
+        bot,
+        state,
+        chat_id,
+        owner_user_id,
+        PendingTorrent::Url(text.text.to_string()),
+    )
+        .await
 }
 
 async fn add_torrent_by_file(
     bot: &Bot,
     state: &BotState,
     document: &Box<Document>,
+    chat_id: ChatIdKind,
+    owner_user_id: i64,
 ) -> anyhow::Result<()> {
     let file_id = &document.document.file_id;
     let file_info = bot.send(GetFile::new(file_id.deref())).await?;
     let file_path = file_info
         .file_path
         .ok_or(anyhow!("File path not available after fet file info"))?;
-    let file = download_torrent_file(&state, &bot.token, &file_path).await?;
-    add_new_torrent(&state, RequestType::File(&Bytes::from(file))).await?;
-    Ok(())
+    let file = download_torrent_file(state, &bot.token, &file_path).await?;
+    prompt_category(bot, state, chat_id, owner_user_id, PendingTorrent::File(file)).await
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -183,6 +496,10 @@ struct Configuration {
     username: String,
     password: String,
     url: Option<String>,
+    poll_interval_seconds: Option<u64>,
+    db_path: Option<String>,
+    /// Which download daemon to talk to: `"qbittorrent"` (default) or `"transmission"`.
+    backend: Option<String>,
 }
 
 fn load_config() -> anyhow::Result<Configuration> {
@@ -207,13 +524,59 @@ async fn download_torrent_file(
 async fn add_new_torrent<'a>(
     state: &BotState,
     request_type: RequestType<'a>,
+    options: &AddOptions,
+    owner_user_id: i64,
 ) -> anyhow::Result<()> {
-    let client = QBittorrentClient::new(&state.options.url).await?;
-    client
-        .login(&state.options.username, &state.options.password)
-        .await?;
+    let hashes_before: HashSet<String> = state
+        .download_client
+        .list()
+        .await?
+        .into_iter()
+        .map(|torrent| torrent.hash)
+        .collect();
+
+    state.download_client.add(&request_type, options).await?;
+
+    // Magnets/URLs the daemon hasn't resolved yet won't show up in the very next `list()`,
+    // so give it a few short retries before giving up on tracking this torrent.
+    let new_torrents = {
+        let mut found = Vec::new();
+        for attempt in 0..NEW_TORRENT_LOOKUP_RETRIES {
+            found = state
+                .download_client
+                .list()
+                .await?
+                .into_iter()
+                .filter(|torrent| !hashes_before.contains(&torrent.hash))
+                .collect();
+
+            if !found.is_empty() || attempt + 1 == NEW_TORRENT_LOOKUP_RETRIES {
+                break;
+            }
 
-    client.add_new_torrent(request_type).await?;
+            tokio::time::sleep(NEW_TORRENT_LOOKUP_DELAY).await;
+        }
+        found
+    };
+
+    if new_torrents.is_empty() {
+        warn!("Could not identify the newly added torrent by diffing the torrent list");
+    }
+
+    for torrent in new_torrents {
+        let added_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        state
+            .storage
+            .record_added(owner_user_id, &torrent.hash, &torrent.name, added_at)
+            .await?;
+
+        state
+            .track_torrent(torrent.hash, torrent.state, owner_user_id)
+            .await;
+    }
 
     Ok(())
 }
+
+const NEW_TORRENT_LOOKUP_RETRIES: u32 = 5;
+const NEW_TORRENT_LOOKUP_DELAY: Duration = Duration::from_millis(500);
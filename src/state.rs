@@ -1,8 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use crate::client::{DownloadClient, PendingTorrent, QBittorrentClient, TransmissionClient};
+use crate::storage::{SqliteStorage, Storage};
 use crate::util::get_bittorrent_api_url;
 use crate::Configuration;
 use reqwest::Client;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use log::info;
 use telers::client::Reqwest;
@@ -12,6 +15,7 @@ use telers::middlewares::outer::MiddlewareResponse;
 use telers::middlewares::OuterMiddleware;
 use telers::router::Request;
 use telers::FromContext;
+use tokio::sync::RwLock;
 
 #[derive(Clone, FromContext)]
 #[context(key = "state")]
@@ -21,18 +25,33 @@ pub struct BotState {
 
 pub struct Inner {
     pub client: Client,
-    pub options: BitTorrentClientOptions,
+    pub download_client: Arc<dyn DownloadClient>,
+    pub storage: Arc<dyn Storage>,
     allowed_user_ids: HashSet<i64>,
+    tracked_torrents: RwLock<HashMap<String, TrackedTorrent>>,
+    pending_torrents: RwLock<HashMap<u64, PendingTorrentEntry>>,
+    next_pending_id: AtomicU64,
 }
 
-pub struct BitTorrentClientOptions {
-    pub url: String,
-    pub username: String,
-    pub password: String,
+struct PendingTorrentEntry {
+    owner_user_id: i64,
+    torrent: PendingTorrent,
+    /// Categories offered when the torrent was prompted, so the category callback can look
+    /// a choice up by index instead of round-tripping the (potentially long) name through
+    /// `callback_data`.
+    categories: Vec<String>,
+}
+
+const DEFAULT_DB_PATH: &str = "torrents.db";
+const DEFAULT_BACKEND: &str = "qbittorrent";
+
+struct TrackedTorrent {
+    state: String,
+    owner_user_id: i64,
 }
 
 impl BotState {
-    pub fn new(configuration: Configuration) -> anyhow::Result<Self> {
+    pub async fn new(configuration: Configuration) -> anyhow::Result<Self> {
         let allowed_user_ids: HashSet<i64> = configuration.user_id.split(',')
             .map(|id| Ok::<i64, anyhow::Error>(id.parse::<i64>()?))
             .filter_map(Result::ok)
@@ -40,15 +59,59 @@ impl BotState {
 
         info!("allowed user_ids: {:?}", allowed_user_ids);
 
+        let db_path = configuration
+            .db_path
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DB_PATH.to_string());
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&db_path)?);
+
+        let backend = configuration
+            .backend
+            .as_deref()
+            .unwrap_or(DEFAULT_BACKEND);
+
+        let download_client: Arc<dyn DownloadClient> = match backend {
+            "transmission" => Arc::new(TransmissionClient::new(
+                get_bittorrent_api_url(&configuration)?,
+                Some(configuration.username.clone()),
+                Some(configuration.password.clone()),
+            )?),
+            "qbittorrent" => Arc::new(QBittorrentClient::new(
+                get_bittorrent_api_url(&configuration)?,
+                configuration.username.clone(),
+                configuration.password.clone(),
+            )?),
+            other => return Err(anyhow::anyhow!("Unknown download client backend: {other}")),
+        };
+
+        info!("using download client backend: {}", backend);
+
+        // Rehydrate ownership/last-known-state from the DB so completion notifications for
+        // torrents added before a restart keep working.
+        let tracked_torrents: HashMap<String, TrackedTorrent> = storage
+            .list_all()
+            .await?
+            .into_iter()
+            .map(|record| {
+                (
+                    record.infohash,
+                    TrackedTorrent {
+                        state: record.state,
+                        owner_user_id: record.user_id,
+                    },
+                )
+            })
+            .collect();
+
         Ok(Self {
             inner: Arc::new(Inner {
                 allowed_user_ids,
                 client: Client::new(),
-                options: BitTorrentClientOptions {
-                    password: configuration.password.clone(),
-                    username: configuration.username.clone(),
-                    url: get_bittorrent_api_url(&configuration)?,
-                },
+                download_client,
+                storage,
+                tracked_torrents: RwLock::new(tracked_torrents),
+                pending_torrents: RwLock::new(HashMap::new()),
+                next_pending_id: AtomicU64::new(1),
             }),
         })
     }
@@ -56,6 +119,60 @@ impl BotState {
     pub fn user_allowed(&self, user_id: i64) -> bool {
         self.inner.allowed_user_ids.contains(&user_id)
     }
+
+    pub async fn track_torrent(&self, hash: String, state: String, owner_user_id: i64) {
+        self.inner
+            .tracked_torrents
+            .write()
+            .await
+            .insert(hash, TrackedTorrent { state, owner_user_id });
+    }
+
+    /// Updates the last known state of a tracked torrent, returning the previous state if any.
+    pub async fn update_tracked_state(&self, hash: &str, new_state: &str) -> Option<String> {
+        let mut tracked_torrents = self.inner.tracked_torrents.write().await;
+        tracked_torrents
+            .get_mut(hash)
+            .map(|torrent| std::mem::replace(&mut torrent.state, new_state.to_string()))
+    }
+
+    pub async fn owner_of(&self, hash: &str) -> Option<i64> {
+        self.inner
+            .tracked_torrents
+            .read()
+            .await
+            .get(hash)
+            .map(|torrent| torrent.owner_user_id)
+    }
+
+    /// Stores a torrent awaiting a category choice and returns the id used to retrieve it,
+    /// so several torrents can be pending for the same user at once.
+    pub async fn set_pending_torrent(
+        &self,
+        owner_user_id: i64,
+        torrent: PendingTorrent,
+        categories: Vec<String>,
+    ) -> u64 {
+        let id = self.inner.next_pending_id.fetch_add(1, Ordering::Relaxed);
+        self.inner.pending_torrents.write().await.insert(
+            id,
+            PendingTorrentEntry {
+                owner_user_id,
+                torrent,
+                categories,
+            },
+        );
+        id
+    }
+
+    pub async fn take_pending_torrent(&self, id: u64) -> Option<(i64, PendingTorrent, Vec<String>)> {
+        self.inner
+            .pending_torrents
+            .write()
+            .await
+            .remove(&id)
+            .map(|entry| (entry.owner_user_id, entry.torrent, entry.categories))
+    }
 }
 
 impl Deref for BotState {
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn record_added(
+        &self,
+        user_id: i64,
+        infohash: &str,
+        name: &str,
+        added_at: i64,
+    ) -> anyhow::Result<()>;
+
+    async fn list_for_user(&self, user_id: i64) -> anyhow::Result<Vec<TorrentRecord>>;
+
+    /// All recorded torrents across every user, used to rehydrate in-memory tracking state
+    /// (ownership, last known state) on startup.
+    async fn list_all(&self) -> anyhow::Result<Vec<TorrentRecord>>;
+
+    async fn update_state(&self, infohash: &str, state: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct TorrentRecord {
+    pub infohash: String,
+    pub user_id: i64,
+    pub name: String,
+    pub added_at: i64,
+    pub state: String,
+}
+
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(db_path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(db_path)?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS torrents (
+                infohash TEXT PRIMARY KEY,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                added_at INTEGER NOT NULL,
+                state TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn record_added(
+        &self,
+        user_id: i64,
+        infohash: &str,
+        name: &str,
+        added_at: i64,
+    ) -> anyhow::Result<()> {
+        let connection = self.connection.lock().await;
+        connection.execute(
+            "INSERT OR REPLACE INTO torrents (infohash, user_id, name, added_at, state)
+             VALUES (?1, ?2, ?3, ?4, COALESCE((SELECT state FROM torrents WHERE infohash = ?1), ''))",
+            params![infohash, user_id, name, added_at],
+        )?;
+        Ok(())
+    }
+
+    async fn list_for_user(&self, user_id: i64) -> anyhow::Result<Vec<TorrentRecord>> {
+        let connection = self.connection.lock().await;
+        let mut statement = connection.prepare(
+            "SELECT infohash, user_id, name, added_at, state FROM torrents WHERE user_id = ?1 ORDER BY added_at DESC",
+        )?;
+
+        let records = statement
+            .query_map(params![user_id], torrent_record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<TorrentRecord>> {
+        let connection = self.connection.lock().await;
+        let mut statement = connection
+            .prepare("SELECT infohash, user_id, name, added_at, state FROM torrents ORDER BY added_at DESC")?;
+
+        let records = statement
+            .query_map([], torrent_record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    async fn update_state(&self, infohash: &str, state: &str) -> anyhow::Result<()> {
+        let connection = self.connection.lock().await;
+        connection.execute(
+            "UPDATE torrents SET state = ?1 WHERE infohash = ?2",
+            params![state, infohash],
+        )?;
+        Ok(())
+    }
+}
+
+fn torrent_record_from_row(row: &rusqlite::Row) -> rusqlite::Result<TorrentRecord> {
+    Ok(TorrentRecord {
+        infohash: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        added_at: row.get(3)?,
+        state: row.get(4)?,
+    })
+}
@@ -60,6 +60,31 @@ pub fn run_in_docker() -> bool {
         .any(|x| Path::new(x).exists())
 }
 
+pub fn render_progress_bar(progress: f64, width: usize) -> String {
+    let filled = (progress.clamp(0.0, 1.0) * width as f64).round() as usize;
+    let filled = filled.min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+pub fn format_eta(seconds: i64) -> String {
+    if seconds < 0 || seconds >= 8_640_000 {
+        return "∞".to_string();
+    }
+
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    format!("{}ч {}м", hours, minutes)
+}
+
+pub fn format_speed(bytes_per_sec: i64) -> String {
+    let kb = bytes_per_sec as f64 / 1024.0;
+    if kb < 1024.0 {
+        format!("{:.0} КБ/с", kb)
+    } else {
+        format!("{:.1} МБ/с", kb / 1024.0)
+    }
+}
+
 pub fn get_bittorrent_api_url(configuration: &Configuration) -> anyhow::Result<String> {
     if let Some(url) = &configuration.url {
         info!("using provided address from config {}", url);